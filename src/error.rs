@@ -0,0 +1,64 @@
+use std::fmt;
+
+use actix_web::{error::ResponseError, http::StatusCode, HttpResponse};
+
+/// The category of failure behind an [`ApiError`].
+///
+/// Each variant maps to the HTTP status that should be returned to the
+/// client; the attached `String` is the message returned to the client in the
+/// `errors` list of the JSON error body.
+#[derive(Debug)]
+pub enum ApiErrorKind {
+    Internal(String),
+    Unauthorized(String),
+    Db(String),
+}
+
+#[derive(Debug)]
+pub struct ApiError {
+    pub kind: ApiErrorKind,
+    pub status: StatusCode,
+}
+
+impl From<ApiErrorKind> for ApiError {
+    fn from(kind: ApiErrorKind) -> Self {
+        let status = match kind {
+            ApiErrorKind::Internal(..) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiErrorKind::Unauthorized(..) => StatusCode::UNAUTHORIZED,
+            ApiErrorKind::Db(..) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        ApiError { kind, status }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ApiErrorKind::Internal(msg) => write!(f, "{}", msg),
+            ApiErrorKind::Unauthorized(msg) => write!(f, "{}", msg),
+            ApiErrorKind::Db(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(serde_json::json!({
+            "status": self.status.as_u16(),
+            "errors": [self.to_string()],
+        }))
+    }
+}
+
+impl From<r2d2::Error> for ApiError {
+    fn from(err: r2d2::Error) -> Self {
+        ApiErrorKind::Db(err.to_string()).into()
+    }
+}
@@ -0,0 +1,6 @@
+#[macro_use]
+extern crate log;
+
+pub mod error;
+pub mod settings;
+pub mod tokenserver;
@@ -0,0 +1,25 @@
+/// Secrets loaded from the environment/config that must never be logged.
+#[derive(Clone, Debug)]
+pub struct Secrets {
+    pub master_secret: Vec<u8>,
+}
+
+/// Sizing/TTL knobs for the tokenserver's in-process caches.
+#[derive(Clone, Debug)]
+pub struct TokenserverCacheSettings {
+    pub verify_cache_ttl_seconds: u64,
+    pub verify_cache_capacity: usize,
+    pub user_cache_ttl_seconds: u64,
+    pub user_cache_capacity: usize,
+}
+
+impl Default for TokenserverCacheSettings {
+    fn default() -> Self {
+        Self {
+            verify_cache_ttl_seconds: 300,
+            verify_cache_capacity: 10_000,
+            user_cache_ttl_seconds: 60,
+            user_cache_capacity: 10_000,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use cached::{Cached, TimedCache};
+use sha2::{Digest, Sha256};
+
+use super::db::models::TokenserverUser;
+use super::oauth::VerifyOauthTokenResponse;
+use crate::settings::TokenserverCacheSettings;
+
+/// Sizing/TTL knobs for one of the caches below, configurable through
+/// `ServerState`/settings.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheConfig {
+    pub ttl_seconds: u64,
+    pub capacity: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheCounters {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Memoizes OAuth verification results and tokenserver user lookups for a
+/// short TTL, since both are hit on every request under the repeated-sync
+/// traffic pattern. Entries are proactively evicted by
+/// [`TokenserverCache::invalidate_user`] whenever provisioning changes a
+/// user's `generation`/`client_state`, so the TTL only needs to bound
+/// staleness in the uncommon case, not the common one.
+pub struct TokenserverCache {
+    verify_cache: Mutex<TimedCache<String, VerifyOauthTokenResponse>>,
+    user_cache: Mutex<TimedCache<(String, i32), TokenserverUser>>,
+    verify_counters: AtomicCounters,
+    user_counters: AtomicCounters,
+}
+
+#[derive(Default)]
+struct AtomicCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl TokenserverCache {
+    pub fn new(verify_config: CacheConfig, user_config: CacheConfig) -> Self {
+        Self {
+            verify_cache: Mutex::new(TimedCache::with_size_and_lifespan(
+                verify_config.capacity,
+                verify_config.ttl_seconds,
+            )),
+            user_cache: Mutex::new(TimedCache::with_size_and_lifespan(
+                user_config.capacity,
+                user_config.ttl_seconds,
+            )),
+            verify_counters: AtomicCounters::default(),
+            user_counters: AtomicCounters::default(),
+        }
+    }
+
+    /// Builds the cache from the configured TTL/capacity settings, so the
+    /// knobs in `TokenserverCacheSettings` actually take effect.
+    pub fn from_settings(settings: &TokenserverCacheSettings) -> Self {
+        Self::new(
+            CacheConfig {
+                ttl_seconds: settings.verify_cache_ttl_seconds,
+                capacity: settings.verify_cache_capacity,
+            },
+            CacheConfig {
+                ttl_seconds: settings.user_cache_ttl_seconds,
+                capacity: settings.user_cache_capacity,
+            },
+        )
+    }
+
+    pub fn get_verify(&self, token: &str) -> Option<VerifyOauthTokenResponse> {
+        let key = hash_token(token);
+        let mut cache = self.verify_cache.lock().unwrap();
+        let hit = cache.cache_get(&key).cloned();
+        record(&self.verify_counters, hit.is_some());
+
+        hit
+    }
+
+    pub fn set_verify(&self, token: &str, value: VerifyOauthTokenResponse) {
+        let key = hash_token(token);
+        self.verify_cache.lock().unwrap().cache_set(key, value);
+    }
+
+    pub fn get_user(&self, email: &str, service_id: i32) -> Option<TokenserverUser> {
+        let key = (email.to_owned(), service_id);
+        let mut cache = self.user_cache.lock().unwrap();
+        let hit = cache.cache_get(&key).cloned();
+        record(&self.user_counters, hit.is_some());
+
+        hit
+    }
+
+    pub fn set_user(&self, email: &str, service_id: i32, value: TokenserverUser) {
+        let key = (email.to_owned(), service_id);
+        self.user_cache.lock().unwrap().cache_set(key, value);
+    }
+
+    /// Evicts the cached user row, used after provisioning changes
+    /// `generation`/`client_state` so a stale entry can't be served again
+    /// before its TTL expires.
+    pub fn invalidate_user(&self, email: &str, service_id: i32) {
+        let key = (email.to_owned(), service_id);
+        self.user_cache.lock().unwrap().cache_remove(&key);
+    }
+
+    pub fn verify_counters(&self) -> CacheCounters {
+        self.verify_counters.snapshot()
+    }
+
+    pub fn user_counters(&self) -> CacheCounters {
+        self.user_counters.snapshot()
+    }
+}
+
+impl AtomicCounters {
+    fn snapshot(&self) -> CacheCounters {
+        CacheCounters {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn record(counters: &AtomicCounters, hit: bool) {
+    if hit {
+        counters.hits.fetch_add(1, Ordering::Relaxed);
+    } else {
+        counters.misses.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Tokens are bearer credentials, so we never hold one in memory as a cache
+/// key — only its digest.
+fn hash_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+
+    hex::encode(hasher.finalize())
+}
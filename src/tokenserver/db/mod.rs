@@ -0,0 +1,8 @@
+pub mod models;
+pub mod params;
+
+pub use models::{Db, DbPool, PoolState, TokenserverUser};
+pub use params::GetUser;
+
+/// The Sync 1.5 service id, as registered in the tokenserver `services` table.
+pub const SYNC_1_5_SERVICE_ID: i32 = 1;
@@ -0,0 +1,76 @@
+use async_trait::async_trait;
+
+use crate::error::ApiError;
+
+use super::params;
+
+/// A tokenserver user row, as stored in the tokenserver database.
+#[derive(Debug, Clone, Default)]
+pub struct TokenserverUser {
+    pub uid: i64,
+    pub node: String,
+    pub generation: i64,
+    pub keys_changed_at: Option<i64>,
+    pub client_state: Vec<u8>,
+    pub replaced_at: Option<i64>,
+}
+
+#[async_trait]
+pub trait Db: Send {
+    /// Looks up the user row for an `(email, service_id)` pair, if one
+    /// exists yet.
+    async fn get_user(
+        &self,
+        params: params::GetUser,
+    ) -> Result<Option<TokenserverUser>, ApiError>;
+
+    /// Creates the first row for a previously-unseen `(email, service_id)`
+    /// pair.
+    async fn post_user(&self, params: params::PostUser) -> Result<TokenserverUser, ApiError>;
+
+    /// Updates `generation`/`keys_changed_at` on an existing row in place.
+    ///
+    /// Implementations MUST enforce the monotonicity invariant
+    /// authoritatively at the write itself — e.g.
+    /// `UPDATE ... SET generation = GREATEST(generation, ?),
+    /// keys_changed_at = GREATEST(keys_changed_at, ?) ... RETURNING *` —
+    /// rather than trusting that the caller already checked a cached row.
+    /// A per-process cache can lag behind a write another instance already
+    /// made (instance A caches generation 5 while the DB has advanced to 7
+    /// elsewhere); a blind `UPDATE` driven by that stale read would regress
+    /// the stored value. The returned row reflects whatever the database
+    /// actually holds after the guarded write, which may differ from the
+    /// `generation`/`keys_changed_at` that were requested.
+    async fn put_user(&self, params: params::PutUser) -> Result<TokenserverUser, ApiError>;
+
+    /// Retires the current row (`replaced_at = now`) and inserts a fresh
+    /// one for a key rotation.
+    async fn replace_user(
+        &self,
+        params: params::ReplaceUser,
+    ) -> Result<TokenserverUser, ApiError>;
+
+    async fn check(&self) -> Result<bool, ApiError>;
+}
+
+/// A snapshot of a [`DbPool`]'s connection counts, as reported by the
+/// underlying r2d2 pool.
+///
+/// `idle == 0` on its own is not exhaustion — it's normal at peak load as
+/// long as the pool can still grow. Only `active >= max_size && idle == 0`
+/// means every connection the pool is allowed to hold is checked out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoolState {
+    pub max_size: u32,
+    pub idle: u32,
+    pub active: u32,
+}
+
+/// A handle to a pool of [`Db`] connections.
+pub trait DbPool: Send + Sync {
+    fn get(&self) -> Result<Box<dyn Db>, r2d2::Error>;
+
+    /// Current idle/active connection counts, for the deep heartbeat to
+    /// surface pool saturation.
+    fn state(&self) -> PoolState;
+}
@@ -0,0 +1,41 @@
+/// Parameters for [`super::models::Db::get_user`].
+#[derive(Debug, Clone)]
+pub struct GetUser {
+    pub email: String,
+    pub service_id: i32,
+}
+
+/// Parameters for [`super::models::Db::post_user`]: creates the first row
+/// for a previously-unseen `(email, service_id)` pair.
+#[derive(Debug, Clone)]
+pub struct PostUser {
+    pub email: String,
+    pub service_id: i32,
+    pub generation: i64,
+    pub keys_changed_at: Option<i64>,
+    pub client_state: Vec<u8>,
+    pub node: String,
+}
+
+/// Parameters for [`super::models::Db::put_user`]: advances
+/// `generation`/`keys_changed_at` on the existing row without touching its
+/// `client_state` or `node`.
+#[derive(Debug, Clone)]
+pub struct PutUser {
+    pub uid: i64,
+    pub generation: i64,
+    pub keys_changed_at: Option<i64>,
+}
+
+/// Parameters for [`super::models::Db::replace_user`]: retires `uid` and
+/// inserts a fresh row for a `client_state` change (key rotation).
+#[derive(Debug, Clone)]
+pub struct ReplaceUser {
+    pub uid: i64,
+    pub email: String,
+    pub service_id: i32,
+    pub generation: i64,
+    pub keys_changed_at: Option<i64>,
+    pub client_state: Vec<u8>,
+    pub node: String,
+}
@@ -13,8 +13,9 @@ use serde_json::Value;
 use sha2::Sha256;
 use std::collections::HashMap;
 
-use super::db::{self, models::Db, params::GetUser};
+use super::db::{self, models::Db};
 use super::extractors::TokenserverRequest;
+use super::provisioning::{self, ProvisionUserParams};
 use super::support::Tokenlib;
 use super::ServerState;
 use crate::{
@@ -33,9 +34,32 @@ pub struct TokenserverResult {
     hashed_fxa_uid: String,
 }
 
+/// Thin wrapper around [`handle_tokenserver_request`] so the total-request
+/// timer is emitted on every exit path — including provisioning failures
+/// (stale generation/keys_changed_at, DB errors) — not just the success
+/// path the inner function returns on.
 pub async fn get_tokenserver_result(
     tokenserver_request: TokenserverRequest,
     request: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let request_start = tokenserver_request.request_start;
+    let metrics = request
+        .app_data::<Data<Option<ServerState>>>()
+        .and_then(|state| state.as_ref().as_ref())
+        .map(|state| state.metrics.clone());
+
+    let result = handle_tokenserver_request(tokenserver_request, request).await;
+
+    if let Some(metrics) = &metrics {
+        metrics.time_request(request_start.elapsed());
+    }
+
+    result
+}
+
+async fn handle_tokenserver_request(
+    tokenserver_request: TokenserverRequest,
+    request: HttpRequest,
 ) -> Result<HttpResponse, Error> {
     let state = request
         .app_data::<Data<Option<ServerState>>>()
@@ -50,12 +74,22 @@ pub async fn get_tokenserver_result(
 
     let user_email = format!("{}@{}", tokenserver_request.fxa_uid, state.fxa_email_domain);
     let tokenserver_user = {
-        let params = GetUser {
+        let params = ProvisionUserParams {
             email: user_email.clone(),
             service_id: db::SYNC_1_5_SERVICE_ID,
+            generation: tokenserver_request.generation,
+            keys_changed_at: tokenserver_request.keys_changed_at,
+            client_state: tokenserver_request.client_state.clone(),
         };
 
-        db.get_user(params).await?
+        provisioning::provision_user(
+            db.as_ref(),
+            state.node_allocator.as_ref(),
+            &state.cache,
+            &state.metrics,
+            params,
+        )
+        .await?
     };
 
     let fxa_metrics_hash_secret = state.fxa_metrics_hash_secret.clone().into_bytes();
@@ -69,14 +103,16 @@ pub async fn get_tokenserver_result(
     };
 
     let fxa_kid = {
+        // `tokenserver_user` has already been reconciled by `provision_user`,
+        // so its `client_state`/`keys_changed_at` are authoritative — never
+        // fall back to the request's `generation` here, or a key rotation
+        // and a timestamp would be indistinguishable.
         let client_state_b64 =
             base64::encode_config(&tokenserver_user.client_state, base64::URL_SAFE_NO_PAD);
 
         format!(
             "{:013}-{:}",
-            tokenserver_user
-                .keys_changed_at
-                .unwrap_or(tokenserver_request.generation),
+            tokenserver_user.keys_changed_at.unwrap_or(0),
             client_state_b64
         )
     };
@@ -104,7 +140,7 @@ pub async fn get_tokenserver_result(
                 node: tokenserver_user.node.clone(),
                 fxa_kid,
                 fxa_uid: tokenserver_request.fxa_uid.clone(),
-                hashed_device_id,
+                hashed_device_id: hashed_device_id.clone(),
                 hashed_fxa_uid: hashed_fxa_uid.to_owned(),
                 expires,
                 uid: tokenserver_user.uid,
@@ -125,6 +161,13 @@ pub async fn get_tokenserver_result(
         hashed_fxa_uid: hashed_fxa_uid.to_owned(),
     };
 
+    state.metrics.incr_token_issued(
+        db::SYNC_1_5_SERVICE_ID,
+        &tokenserver_user.node,
+        hashed_fxa_uid,
+        &hashed_device_id,
+    );
+
     Ok(HttpResponse::build(StatusCode::OK).json(result))
 }
 
@@ -151,33 +194,102 @@ fn internal_error(message: &str) -> HttpResponse {
     HttpResponse::InternalServerError().body("")
 }
 
-pub async fn heartbeat(db: Box<dyn Db>) -> Result<HttpResponse, Error> {
+/// `__lbheartbeat__`: a liveness check for the load balancer. Doesn't touch
+/// the database or any dependency — if the process can answer HTTP at all,
+/// this returns 200.
+pub async fn lbheartbeat() -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// `__version__`: build metadata, so operators can tell which build is
+/// running without shelling into the host.
+pub async fn version() -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "commit": option_env!("CIRCLE_SHA1").unwrap_or("unknown"),
+        "source": "https://github.com/mozilla-services/syncstorage-rs",
+    })))
+}
+
+/// `__heartbeat__`: a deep check of every dependency this service needs to
+/// issue tokens, so operators can tell "DB down" apart from "pool
+/// starvation" apart from "FxA is unreachable". Returns 503 if any
+/// dependency is unhealthy, including when the connection pool is exhausted
+/// (no idle connections and every connection it may hold checked out).
+pub async fn heartbeat(state: Data<Option<ServerState>>) -> Result<HttpResponse, Error> {
+    let state = state
+        .as_ref()
+        .as_ref()
+        .ok_or_else(|| internal_error("Could not load the app state"))?;
+
     let mut checklist = HashMap::new();
     checklist.insert(
         "version".to_owned(),
         Value::String(env!("CARGO_PKG_VERSION").to_owned()),
     );
 
-    match db.check().await {
-        Ok(result) => {
-            if result {
-                checklist.insert("database".to_owned(), Value::from("Ok"));
-            } else {
-                checklist.insert("database".to_owned(), Value::from("Err"));
-                checklist.insert(
-                    "database_msg".to_owned(),
-                    Value::from("check failed without error"),
-                );
-            };
-            let status = if result { "Ok" } else { "Err" };
-            checklist.insert("status".to_owned(), Value::from(status));
-            Ok(HttpResponse::Ok().json(checklist))
-        }
-        Err(e) => {
-            error!("Heartbeat error: {:?}", e);
-            checklist.insert("status".to_owned(), Value::from("Err"));
-            checklist.insert("database".to_owned(), Value::from("Unknown"));
-            Ok(HttpResponse::ServiceUnavailable().json(checklist))
+    let pool_state = state.db_pool.state();
+    // A merely-busy pool (idle == 0, active < max_size) is normal at peak
+    // load and can still grow; only flag exhaustion once it's actually
+    // pinned at its configured ceiling with nothing idle.
+    let pool_exhausted = pool_state.idle == 0 && pool_state.active >= pool_state.max_size;
+    checklist.insert(
+        "database_pool".to_owned(),
+        serde_json::json!({
+            "max_size": pool_state.max_size,
+            "idle": pool_state.idle,
+            "active": pool_state.active,
+            "exhausted": pool_exhausted,
+        }),
+    );
+
+    let verify_counters = state.cache.verify_counters();
+    let user_counters = state.cache.user_counters();
+    checklist.insert(
+        "cache".to_owned(),
+        serde_json::json!({
+            "verify_hits": verify_counters.hits,
+            "verify_misses": verify_counters.misses,
+            "user_hits": user_counters.hits,
+            "user_misses": user_counters.misses,
+        }),
+    );
+
+    let database_ok = match state.db_pool.get() {
+        Ok(db) => db.check().await.unwrap_or_else(|err| {
+            error!("Heartbeat database check failed: {:?}", err);
+            false
+        }),
+        Err(err) => {
+            error!("Heartbeat could not get a database connection: {:?}", err);
+            false
         }
-    }
+    };
+    checklist.insert(
+        "database".to_owned(),
+        Value::from(if database_ok { "Ok" } else { "Err" }),
+    );
+
+    let fxa_ok = state.oauth_verifier.check_server().await.unwrap_or_else(|err| {
+        error!("Heartbeat FxA OAuth check failed: {:?}", err);
+        false
+    });
+    checklist.insert(
+        "fxa_oauth".to_owned(),
+        Value::from(if fxa_ok { "Ok" } else { "Err" }),
+    );
+
+    let healthy = database_ok && fxa_ok && !pool_exhausted;
+    checklist.insert(
+        "status".to_owned(),
+        Value::from(if healthy { "Ok" } else { "Err" }),
+    );
+
+    let status_code = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    Ok(HttpResponse::build(status_code).json(checklist))
 }
@@ -0,0 +1,89 @@
+use std::net::UdpSocket;
+use std::sync::Arc;
+use std::time::Duration;
+
+use cadence::{BufferedUdpMetricSink, Counted, NopMetricSink, QueuingMetricSink, StatsdClient, Timed};
+
+const METRICS_PREFIX: &str = "syncstorage.tokenserver";
+
+/// Publishes StatsD timers/counters for token issuance, tagged with the
+/// privacy-preserving hashed identifiers the handler already computes for
+/// this purpose. Falls back to a no-op sink when no metrics host is
+/// configured, so a missing/unreachable StatsD host never affects request
+/// handling — failures to emit are logged and otherwise ignored.
+#[derive(Clone)]
+pub struct Metrics {
+    client: Arc<StatsdClient>,
+}
+
+impl Metrics {
+    pub fn new(host: Option<(String, u16)>) -> Self {
+        let client = host
+            .and_then(|(host, port)| match Self::udp_client(&host, port) {
+                Ok(client) => Some(client),
+                Err(err) => {
+                    error!("Failed to initialize StatsD sink, metrics disabled: {}", err);
+                    None
+                }
+            })
+            .unwrap_or_else(Self::nop_client);
+
+        Self {
+            client: Arc::new(client),
+        }
+    }
+
+    fn udp_client(host: &str, port: u16) -> std::io::Result<StatsdClient> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_nonblocking(true)?;
+        let sink = QueuingMetricSink::from(BufferedUdpMetricSink::from((host, port), socket)?);
+
+        Ok(StatsdClient::from_sink(METRICS_PREFIX, sink))
+    }
+
+    fn nop_client() -> StatsdClient {
+        StatsdClient::from_sink(METRICS_PREFIX, NopMetricSink)
+    }
+
+    /// A token was successfully issued for `service_id` on `node`.
+    pub fn incr_token_issued(&self, service_id: i32, node: &str, hashed_fxa_uid: &str, hashed_device_id: &str) {
+        self.log_err(
+            self.client
+                .count_with_tags("token.issued", 1)
+                .with_tag("service", &service_id.to_string())
+                .with_tag("node", node)
+                .with_tag("hashed_fxa_uid", hashed_fxa_uid)
+                .with_tag("hashed_device_id", hashed_device_id)
+                .try_send(),
+        );
+    }
+
+    /// Request handling failed with the given class: `invalid_token`,
+    /// `stale_generation`, `stale_keys_changed_at`, or `verification_error`.
+    pub fn incr_failure(&self, class: &str) {
+        self.log_err(
+            self.client
+                .count_with_tags("token.failure", 1)
+                .with_tag("class", class)
+                .try_send(),
+        );
+    }
+
+    pub fn time_request(&self, duration: Duration) {
+        self.log_err(self.client.time("request.duration", as_millis(duration)));
+    }
+
+    pub fn time_get_user(&self, duration: Duration) {
+        self.log_err(self.client.time("get_user.duration", as_millis(duration)));
+    }
+
+    fn log_err<T, E: std::fmt::Display>(&self, result: Result<T, E>) {
+        if let Err(err) = result {
+            warn!("Failed to emit metric: {}", err);
+        }
+    }
+}
+
+fn as_millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
@@ -0,0 +1,30 @@
+pub mod cache;
+pub mod db;
+pub mod extractors;
+pub mod handlers;
+pub mod metrics;
+pub mod node;
+pub mod oauth;
+pub mod provisioning;
+pub mod support;
+
+use std::sync::Arc;
+
+use self::cache::TokenserverCache;
+use self::db::DbPool;
+use self::metrics::Metrics;
+use self::node::NodeAllocator;
+use self::oauth::OauthVerifier;
+
+/// Shared state for the tokenserver handlers, constructed once at startup
+/// and handed to each request via `actix_web::web::Data`.
+#[derive(Clone)]
+pub struct ServerState {
+    pub db_pool: Arc<dyn DbPool>,
+    pub fxa_email_domain: String,
+    pub fxa_metrics_hash_secret: String,
+    pub oauth_verifier: OauthVerifier,
+    pub node_allocator: Arc<dyn NodeAllocator>,
+    pub cache: Arc<TokenserverCache>,
+    pub metrics: Metrics,
+}
@@ -0,0 +1,22 @@
+use crate::error::ApiError;
+
+/// Chooses the storage node a user's rows should live on.
+///
+/// This is pluggable so that the default "keep using the current node"
+/// behavior can later be swapped for a least-loaded-node strategy without
+/// touching the provisioning logic that calls it.
+pub trait NodeAllocator: Send + Sync {
+    fn allocate_node(&self) -> Result<String, ApiError>;
+}
+
+/// Always hands out the single statically-configured node. This is the
+/// default allocator until a real load-balancing strategy is implemented.
+pub struct CurrentNodeAllocator {
+    pub node: String,
+}
+
+impl NodeAllocator for CurrentNodeAllocator {
+    fn allocate_node(&self) -> Result<String, ApiError> {
+        Ok(self.node.clone())
+    }
+}
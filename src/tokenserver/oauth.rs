@@ -0,0 +1,98 @@
+use serde::Deserialize;
+
+use crate::error::{ApiError, ApiErrorKind};
+
+/// The OAuth scope that must be present on a token for it to be accepted by
+/// Sync. Tokens that lack this scope are treated the same as an invalid
+/// token.
+pub const SYNC_SCOPE: &str = "https://identity.mozilla.com/apps/oldsync";
+
+/// The claims FxA returns from a successful `/v1/verify` introspection.
+#[derive(Debug, Clone, Deserialize)]
+pub struct VerifyOauthTokenResponse {
+    pub user: String,
+    #[serde(default)]
+    pub scope: Vec<String>,
+    pub generation: Option<i64>,
+    pub client_id: String,
+}
+
+/// Verifies an FxA OAuth access token by introspecting it against the
+/// configured FxA OAuth server, mirroring the behavior of the FxA client
+/// libraries' own verifier.
+#[derive(Clone)]
+pub struct OauthVerifier {
+    pub server_url: String,
+    /// Overrides the URL probed by `check_server`. Defaults to
+    /// `{server_url}/__heartbeat__`, which is what FxA's OAuth server
+    /// actually serves; the OIDC discovery document lives on the
+    /// issuer/content server instead, not here.
+    pub heartbeat_url: Option<String>,
+    http: reqwest::Client,
+}
+
+impl OauthVerifier {
+    pub fn new(server_url: String) -> Self {
+        Self {
+            server_url,
+            heartbeat_url: None,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn verify(&self, token: &str) -> Result<VerifyOauthTokenResponse, ApiError> {
+        let url = format!("{}/v1/verify", self.server_url);
+        // Transport/decode failures mean FxA is unreachable or misbehaving,
+        // not that the token is bad — report those as `Internal` (500) so
+        // clients retry instead of re-authenticating into a thundering herd.
+        // `Unauthorized` (401) is reserved for FxA actually telling us the
+        // token is invalid: a non-success status or a missing Sync scope.
+        let response = self
+            .http
+            .post(&url)
+            .json(&serde_json::json!({ "token": token }))
+            .send()
+            .await
+            .map_err(|err| ApiErrorKind::Internal(err.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(ApiErrorKind::Unauthorized(format!(
+                "FxA OAuth token introspection failed with status {}",
+                response.status()
+            ))
+            .into());
+        }
+
+        let claims: VerifyOauthTokenResponse = response
+            .json()
+            .await
+            .map_err(|err| ApiErrorKind::Internal(err.to_string()))?;
+
+        if !claims.scope.iter().any(|scope| scope == SYNC_SCOPE) {
+            return Err(ApiErrorKind::Unauthorized(
+                "OAuth token is missing the Sync scope".to_owned(),
+            )
+            .into());
+        }
+
+        Ok(claims)
+    }
+
+    /// A lightweight reachability check for the deep heartbeat: does the
+    /// configured FxA OAuth server respond at all? This doesn't exercise
+    /// token introspection, just connectivity.
+    pub async fn check_server(&self) -> Result<bool, ApiError> {
+        let url = self
+            .heartbeat_url
+            .clone()
+            .unwrap_or_else(|| format!("{}/__heartbeat__", self.server_url));
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .map_err(|err| ApiErrorKind::Internal(err.to_string()))?;
+
+        Ok(response.status().is_success())
+    }
+}
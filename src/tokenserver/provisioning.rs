@@ -0,0 +1,140 @@
+use std::time::Instant;
+
+use super::cache::TokenserverCache;
+use super::db::{
+    models::{Db, TokenserverUser},
+    params,
+};
+use super::metrics::Metrics;
+use super::node::NodeAllocator;
+use crate::error::{ApiError, ApiErrorKind};
+
+/// The identity presented by an authenticated request, as reconciled by
+/// [`provision_user`] against the stored tokenserver user row.
+#[derive(Debug, Clone)]
+pub struct ProvisionUserParams {
+    pub email: String,
+    pub service_id: i32,
+    pub generation: i64,
+    pub keys_changed_at: Option<i64>,
+    pub client_state: Vec<u8>,
+}
+
+/// Reconciles an authenticated request against the stored user row,
+/// enforcing the Sync tokenserver invariants:
+///
+/// 1. `generation` must never move backwards (stale credential).
+/// 2. `keys_changed_at` must never move backwards.
+/// 3. A changed `client_state` is a key rotation: the old row is marked
+///    `replaced_at` and a fresh row is inserted, possibly on a new node.
+/// 4. Otherwise, if `generation`/`keys_changed_at` advanced, the existing
+///    row is updated in place.
+/// 5. If no row exists yet, one is created and assigned a node.
+///
+/// The checks above run against the (possibly cached, per-process) `stored`
+/// row purely as a fast path/early rejection — they are not the
+/// authoritative guard. The cache is invalidated on write, but in a
+/// multi-instance deployment a different instance can advance the DB out
+/// from under this one between cache fills, so [`Db::put_user`] must
+/// itself enforce monotonicity at the write (a guarded `UPDATE`/`GREATEST`),
+/// never blindly overwrite with the requested values.
+pub async fn provision_user(
+    db: &dyn Db,
+    node_allocator: &dyn NodeAllocator,
+    cache: &TokenserverCache,
+    metrics: &Metrics,
+    request: ProvisionUserParams,
+) -> Result<TokenserverUser, ApiError> {
+    let existing = if let Some(cached) = cache.get_user(&request.email, request.service_id) {
+        Some(cached)
+    } else {
+        let start = Instant::now();
+        let fetched = db
+            .get_user(params::GetUser {
+                email: request.email.clone(),
+                service_id: request.service_id,
+            })
+            .await?;
+        metrics.time_get_user(start.elapsed());
+
+        if let Some(fetched) = fetched.clone() {
+            cache.set_user(&request.email, request.service_id, fetched);
+        }
+
+        fetched
+    };
+
+    let mut changed = false;
+
+    let user = match existing {
+        None => {
+            let node = node_allocator.allocate_node()?;
+
+            changed = true;
+            db.post_user(params::PostUser {
+                email: request.email.clone(),
+                service_id: request.service_id,
+                generation: request.generation,
+                keys_changed_at: request.keys_changed_at,
+                client_state: request.client_state,
+                node,
+            })
+            .await?
+        }
+        Some(stored) => {
+            if request.generation < stored.generation {
+                metrics.incr_failure("stale_generation");
+                return Err(ApiErrorKind::Unauthorized("Stale generation".to_owned()).into());
+            }
+
+            if request.keys_changed_at.unwrap_or(0) < stored.keys_changed_at.unwrap_or(0) {
+                metrics.incr_failure("stale_keys_changed_at");
+                return Err(
+                    ApiErrorKind::Unauthorized("Stale keys_changed_at".to_owned()).into(),
+                );
+            }
+
+            if request.client_state != stored.client_state {
+                let node = node_allocator.allocate_node()?;
+
+                changed = true;
+                db.replace_user(params::ReplaceUser {
+                    uid: stored.uid,
+                    email: request.email.clone(),
+                    service_id: request.service_id,
+                    generation: request.generation,
+                    keys_changed_at: request.keys_changed_at,
+                    client_state: request.client_state,
+                    node,
+                })
+                .await?
+            } else if request.generation > stored.generation
+                || request.keys_changed_at > stored.keys_changed_at
+            {
+                changed = true;
+
+                // Use the row `put_user` returns rather than assuming the
+                // write applied the requested values verbatim: the DB-level
+                // guard may have kept a value another instance already
+                // advanced past this one.
+                db.put_user(params::PutUser {
+                    uid: stored.uid,
+                    generation: request.generation,
+                    keys_changed_at: request.keys_changed_at,
+                })
+                .await?
+            } else {
+                stored
+            }
+        }
+    };
+
+    if changed {
+        // The row we had cached (if any) is now stale; evict it immediately
+        // rather than waiting out the TTL.
+        cache.invalidate_user(&request.email, request.service_id);
+        cache.set_user(&request.email, request.service_id, user.clone());
+    }
+
+    Ok(user)
+}
@@ -0,0 +1,47 @@
+use hmac::{Hmac, Mac, NewMac};
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::error::{ApiError, ApiErrorKind};
+
+/// The payload signed into the token returned to Sync clients.
+#[derive(Debug, Serialize)]
+pub struct MakeTokenPlaintext {
+    pub node: String,
+    pub fxa_kid: String,
+    pub fxa_uid: String,
+    pub hashed_device_id: String,
+    pub hashed_fxa_uid: String,
+    pub expires: u64,
+    pub uid: i64,
+}
+
+/// A minimal reimplementation of Mozilla's `tokenlib`: signs a payload with
+/// the node's shared secret and derives a per-token HAWK secret from it.
+pub struct Tokenlib;
+
+impl Tokenlib {
+    pub fn get_token_and_derived_secret(
+        payload: MakeTokenPlaintext,
+        shared_secret: &str,
+    ) -> Result<(String, String), ApiError> {
+        let payload_json = serde_json::to_vec(&payload)
+            .map_err(|err| ApiErrorKind::Internal(err.to_string()))?;
+        let payload_b64 = base64::encode_config(&payload_json, base64::URL_SAFE_NO_PAD);
+
+        let signature = Self::hmac_hex(payload_b64.as_bytes(), shared_secret.as_bytes())?;
+        let token = format!("{}.{}", payload_b64, signature);
+
+        let derived_secret = Self::hmac_hex(token.as_bytes(), shared_secret.as_bytes())?;
+
+        Ok((token, derived_secret))
+    }
+
+    fn hmac_hex(message: &[u8], key: &[u8]) -> Result<String, ApiError> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key)
+            .map_err(|err| ApiErrorKind::Internal(err.to_string()))?;
+        mac.update(message);
+
+        Ok(hex::encode(mac.finalize().into_bytes()))
+    }
+}